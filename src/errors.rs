@@ -0,0 +1,13 @@
+/// Errors produced by the sparse linear algebra routines
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SprsError {
+    /// A function expected a different storage layout (eg csc vs csr)
+    BadStorageType,
+    /// A triangular solve or factorization hit a zero or missing
+    /// diagonal entry
+    SingularMatrix,
+    /// A Cholesky factorization hit a non-positive diagonal entry, ie
+    /// the input matrix is not symmetric positive definite
+    NotPositiveDefinite,
+}
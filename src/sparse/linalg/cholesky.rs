@@ -0,0 +1,184 @@
+/// Sparse Cholesky factorization
+///
+/// This builds a numeric Cholesky factorization on top of the symbolic
+/// structure computed by the `etree` module: once the nonzero pattern of
+/// `L` is known ahead of time, the numeric phase only has to fill in
+/// values, using a left-looking column update (the column-major
+/// counterpart of the more commonly described up-looking row solve):
+/// for each column `j`, start from `A(j:n-1, j)` and subtract the rank-1
+/// contribution of every earlier column `k` that has a non-zero at row
+/// `j`, then read off the diagonal and the rest of the column with
+/// `L(j,j) = sqrt(A(j,j) - sum_k L(j,k)^2)`.
+
+use num::traits::Float;
+use sparse::{CsMatOwned, CsMatView};
+use errors::SprsError;
+use super::trisolve::{self, Conjugate, Op};
+use super::etree;
+
+/// A Cholesky factorization `A = L * L^T` of a symmetric positive
+/// definite matrix, stored as the lower triangular factor `l`.
+pub struct CholeskyFactorization<N> {
+    l: CsMatOwned<N>,
+}
+
+impl<N: Copy + Float> CholeskyFactorization<N> {
+    /// The lower triangular factor
+    pub fn l(&self) -> CsMatView<N> {
+        self.l.view()
+    }
+}
+
+impl<N: Copy + Float + Conjugate> CholeskyFactorization<N> {
+    /// Solve `A x = b` using this factorization, by chaining a lower
+    /// triangular solve against `L` and an upper triangular solve against
+    /// `L^T`, the latter reusing `L`'s own storage with `Op::Transpose`
+    /// rather than materializing the transpose.
+    pub fn solve(&self, b: &[N]) -> Result<Vec<N>, SprsError> {
+        let mut x = b.to_vec();
+        try!(trisolve::lsolve_csc_dense_rhs(self.l.view(), &mut x));
+        try!(trisolve::lsolve_csc_dense_rhs_op(self.l.view(), &mut x, Op::Transpose));
+        Ok(x)
+    }
+}
+
+fn l_value_at<N: Copy>(l_indptr: &[usize],
+                       l_indices: &[usize],
+                       l_data: &[N],
+                       col: usize,
+                       row: usize)
+                       -> N
+{
+    let start = l_indptr[col];
+    let end = l_indptr[col + 1];
+    let pos = l_indices[start..end]
+                  .iter()
+                  .position(|&r| r == row)
+                  .expect("row should be in column's pattern");
+    l_data[start + pos]
+}
+
+/// Compute the Cholesky factorization of a symmetric positive definite
+/// matrix, given its lower triangular part stored as a csc matrix.
+///
+/// # Errors
+///
+/// Returns `SprsError::NotPositiveDefinite` if a diagonal entry of `L`
+/// would be computed from a non-positive value, which means `mat` is not
+/// symmetric positive definite.
+pub fn cholesky<N>(mat: CsMatView<N>) -> Result<CholeskyFactorization<N>, SprsError>
+    where N: Copy + Float
+{
+    if !mat.is_csc() {
+        return Err(SprsError::BadStorageType);
+    }
+    let n = mat.rows();
+    if mat.cols() != n {
+        panic!("cholesky requires a square matrix");
+    }
+
+    let parent = try!(etree::etree(mat));
+    let patterns = etree::column_patterns(mat, &parent);
+
+    let mut l_indptr = vec![0; n + 1];
+    for j in 0..n {
+        l_indptr[j + 1] = l_indptr[j] + patterns[j].len();
+    }
+    let nnz = l_indptr[n];
+    let mut l_indices = vec![0; nnz];
+    for j in 0..n {
+        let start = l_indptr[j];
+        for (k, &row) in patterns[j].iter().enumerate() {
+            l_indices[start + k] = row;
+        }
+    }
+    let mut l_data = vec![N::zero(); nnz];
+
+    // affects[i] lists the already-computed columns k < i having a
+    // non-zero at row i, so column i's update loop only ever looks at
+    // the columns it actually depends on.
+    let mut affects: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for k in 0..n {
+        for &row in &patterns[k] {
+            if row > k {
+                affects[row].push(k);
+            }
+        }
+    }
+
+    let mut y = vec![N::zero(); n];
+    for j in 0..n {
+        for &row in &patterns[j] {
+            y[row] = N::zero();
+        }
+        let col = mat.outer_view(j).expect("col in bounds");
+        for (row, &val) in col.iter() {
+            if row >= j {
+                y[row] = val;
+            }
+        }
+
+        for &k in &affects[j] {
+            let f = l_value_at(&l_indptr, &l_indices, &l_data, k, j);
+            let start = l_indptr[k];
+            let end = l_indptr[k + 1];
+            for p in start..end {
+                let i = l_indices[p];
+                if i >= j {
+                    y[i] = y[i] - l_data[p] * f;
+                }
+            }
+        }
+
+        if y[j] <= N::zero() {
+            return Err(SprsError::NotPositiveDefinite);
+        }
+        let ljj = y[j].sqrt();
+
+        let start = l_indptr[j];
+        for (k, &row) in patterns[j].iter().enumerate() {
+            l_data[start + k] = if row == j {
+                ljj
+            } else {
+                y[row] / ljj
+            };
+        }
+    }
+
+    let l = CsMatOwned::new_csc((n, n), l_indptr, l_indices, l_data);
+    Ok(CholeskyFactorization { l: l })
+}
+
+#[cfg(test)]
+mod test {
+    use sparse::CsMatOwned;
+    use errors::SprsError;
+
+    #[test]
+    fn cholesky_round_trip() {
+        // |4  6|             L = |2 0|, chosen so that every intermediate
+        // |6 13|                 |3 2|  division/sqrt is exact in binary fp
+        let a = CsMatOwned::new_csc((2, 2),
+                                    vec![0, 2, 3],
+                                    vec![0, 1, 1],
+                                    vec![4., 6., 13.]);
+        let chol = super::cholesky(a.view()).unwrap();
+
+        // b = A * [1, 2]
+        let b = vec![16., 32.];
+        let x = chol.solve(&b).unwrap();
+        assert_eq!(x, vec![1., 2.]);
+    }
+
+    #[test]
+    fn cholesky_not_positive_definite() {
+        // |1 2|   indefinite (determinant = 1 - 4 = -3 < 0): eliminating
+        // |2 1|   column 0 leaves 1 - 2*2 = -3 on the diagonal of column 1
+        let a = CsMatOwned::new_csc((2, 2),
+                                    vec![0, 2, 3],
+                                    vec![0, 1, 1],
+                                    vec![1., 2., 1.]);
+        let res = super::cholesky(a.view());
+        assert_eq!(res.err(), Some(SprsError::NotPositiveDefinite));
+    }
+}
@@ -0,0 +1,258 @@
+/// Sparse LU factorization
+///
+/// This builds a full LU factorization on top of the triangular solve
+/// kernels from the `trisolve` module, using a left-looking algorithm
+/// (Gilbert-Peierls) with partial pivoting: column `j` of `L` and `U` is
+/// obtained by solving `L x = A(:,j)` against the columns of `L` already
+/// computed, reusing `trisolve`'s sparse DFS reachability
+/// (`lsolve_csc_reach`) and numeric column elimination
+/// (`lspsolve_csc_process_col`) directly rather than the general
+/// sparse-RHS solve, since rows `>= j` of the not-yet-pivoted `L` have no
+/// diagonal to look up; the largest magnitude candidate among those rows
+/// is then picked as the diagonal of `U`.
+
+use num::traits::Num;
+use sparse::{CsMatOwned, CsMatView};
+use sparse::vec::CsVecOwned;
+use errors::SprsError;
+use stack::{self, DStack};
+use super::trisolve;
+
+fn abs_val<N: Copy + Num + PartialOrd>(x: N) -> N {
+    if x < N::zero() { N::zero() - x } else { x }
+}
+
+/// An LU factorization of a sparse matrix, stored as the unit lower
+/// triangular factor `l`, the upper triangular factor `u`, and the row
+/// permutation `perm` such that `perm` applied to the rows of the
+/// original matrix yields `l * u`.
+pub struct LuFactorization<N> {
+    l: CsMatOwned<N>,
+    u: CsMatOwned<N>,
+    perm: Vec<usize>,
+}
+
+impl<N: Copy + Num + PartialOrd> LuFactorization<N> {
+    /// The unit lower triangular factor
+    pub fn l(&self) -> CsMatView<N> {
+        self.l.view()
+    }
+
+    /// The upper triangular factor
+    pub fn u(&self) -> CsMatView<N> {
+        self.u.view()
+    }
+
+    /// The row permutation, such that `perm()[i]` is the row of the
+    /// original matrix ending up at pivoted row `i`
+    pub fn perm(&self) -> &[usize] {
+        &self.perm
+    }
+
+    /// Solve `A x = b` using this factorization, by permuting `b` and
+    /// chaining a lower and an upper triangular solve.
+    pub fn solve(&self, b: &[N]) -> Result<Vec<N>, SprsError> {
+        let n = self.perm.len();
+        let mut x: Vec<N> = self.perm.iter().map(|&row| b[row]).collect();
+        try!(trisolve::lsolve_csc_unit_dense_rhs(self.l.view(), &mut x));
+        try!(trisolve::usolve_csc_dense_rhs(self.u.view(), &mut x));
+        Ok(x)
+    }
+}
+
+/// Compute the LU factorization of a square csc matrix, with partial
+/// pivoting.
+///
+/// # Errors
+///
+/// Returns `SprsError::SingularMatrix` if no usable pivot can be found
+/// for a column (ie all candidate entries are zero).
+pub fn lu<N>(mat: CsMatView<N>) -> Result<LuFactorization<N>, SprsError>
+    where N: Copy + Num + PartialOrd
+{
+    if !mat.is_csc() {
+        return Err(SprsError::BadStorageType);
+    }
+    let n = mat.rows();
+    if mat.cols() != n {
+        panic!("Non square matrix passed to lu");
+    }
+
+    let mut l_indptr = vec![0; n + 1];
+    let mut l_indices = Vec::new();
+    let mut l_data = Vec::new();
+    let mut u_indptr = vec![0; n + 1];
+    let mut u_indices = Vec::new();
+    let mut u_data = Vec::new();
+
+    // perm[pivot_pos] is the original row currently assigned to this
+    // pivot position, perm_inv is its inverse mapping
+    let mut perm: Vec<usize> = (0..n).collect();
+    let mut perm_inv: Vec<usize> = (0..n).collect();
+
+    let mut dstack = DStack::with_capacity(2 * n);
+    let mut visited = vec![false; n];
+    let mut x_workspace = vec![N::zero(); n];
+
+    for j in 0..n {
+        for v in visited.iter_mut() {
+            *v = false;
+        }
+        dstack.clear();
+
+        // express A's column j in the current row permutation, so that
+        // it lines up with the rows of L already pivoted
+        let col = mat.outer_view(j).expect("col in bounds");
+        let mut rhs_pairs: Vec<(usize, N)> = col.iter()
+            .map(|(row, &val)| (perm_inv[row], val))
+            .collect();
+        rhs_pairs.sort_by_key(|&(row, _)| row);
+        let (rhs_ind, rhs_val): (Vec<_>, Vec<_>) = rhs_pairs.into_iter()
+                                                             .unzip();
+        let rhs = CsVecOwned::new(n, rhs_ind, rhs_val);
+
+        // L as computed so far, viewed as an (n, n) csc matrix whose
+        // not-yet-computed trailing columns are left empty. Borrows
+        // `l_indices`/`l_data` directly rather than cloning them, since
+        // they already hold exactly the entries built so far; only the
+        // indptr needs its own (small, O(n)) copy to clamp the
+        // not-yet-computed columns to empty.
+        let mut view_indptr = l_indptr.clone();
+        for k in (j + 1)..(n + 1) {
+            view_indptr[k] = l_indices.len();
+        }
+        let l_so_far = CsMatView::new_csc((n, n), &view_indptr, &l_indices, &l_data);
+
+        // Solve L x = rhs against the built part of L only. Rows >= j of
+        // l_so_far are deliberately left empty (not yet pivoted), so they
+        // must never be looked up for a diagonal: any reached row >= j is
+        // implicitly identity there (no elimination has touched it yet),
+        // its value coming from the scatter below, reduced by whatever
+        // contributions processing the columns < j subtracts into it.
+        try!(trisolve::lsolve_csc_reach(l_so_far,
+                                         rhs.view(),
+                                         &mut dstack,
+                                         &mut visited));
+        // rows reached only through fill-in, and row j when it is not
+        // reached at all, start from zero rather than from whatever an
+        // earlier column left in the workspace
+        for &ind in dstack.iter_right().map(stack::extract_stack_val) {
+            x_workspace[ind] = N::zero();
+        }
+        x_workspace[j] = N::zero();
+        rhs.view().scatter(&mut x_workspace);
+        for &ind in dstack.iter_right().map(stack::extract_stack_val) {
+            if ind >= j {
+                continue;
+            }
+            let col = l_so_far.outer_view(ind).expect("ind not in bounds");
+            try!(trisolve::lspsolve_csc_process_col(col, ind, &mut x_workspace));
+        }
+
+        // partial pivoting: largest magnitude entry among the rows not
+        // yet used as a pivot
+        let mut pivot_pos = None;
+        let mut pivot_mag = N::zero();
+        for &row in dstack.iter_right().map(stack::extract_stack_val) {
+            if row < j {
+                continue;
+            }
+            let mag = abs_val(x_workspace[row]);
+            if pivot_pos.is_none() || mag > pivot_mag {
+                pivot_pos = Some(row);
+                pivot_mag = mag;
+            }
+        }
+        let pivot_pos = match pivot_pos {
+            Some(p) if pivot_mag != N::zero() => p,
+            _ => return Err(SprsError::SingularMatrix),
+        };
+
+        if pivot_pos != j {
+            perm.swap(j, pivot_pos);
+            perm_inv[perm[j]] = j;
+            perm_inv[perm[pivot_pos]] = pivot_pos;
+            x_workspace.swap(j, pivot_pos);
+            // the columns of L already built refer to pivoted rows too, so
+            // rows j and pivot_pos trade places there as well
+            for k in 0..j {
+                let (start, end) = (l_indptr[k], l_indptr[k + 1]);
+                let mut col: Vec<(usize, N)> = (start..end).map(|p| {
+                    let row = l_indices[p];
+                    let row = if row == j { pivot_pos } else if row == pivot_pos { j } else { row };
+                    (row, l_data[p])
+                }).collect();
+                col.sort_by_key(|&(row, _)| row);
+                for (p, (row, val)) in col.into_iter().enumerate() {
+                    l_indices[start + p] = row;
+                    l_data[start + p] = val;
+                }
+            }
+        }
+        let pivot = x_workspace[j];
+
+        let mut l_col: Vec<(usize, N)> = vec![(j, N::one())];
+        let mut u_col: Vec<(usize, N)> = vec![(j, pivot)];
+        for &row in dstack.iter_right().map(stack::extract_stack_val) {
+            if row < j {
+                u_col.push((row, x_workspace[row]));
+            } else if row > j {
+                l_col.push((row, x_workspace[row] / pivot));
+            }
+        }
+
+        l_col.sort_by_key(|&(row, _)| row);
+        u_col.sort_by_key(|&(row, _)| row);
+        for (row, val) in l_col {
+            l_indices.push(row);
+            l_data.push(val);
+        }
+        for (row, val) in u_col {
+            u_indices.push(row);
+            u_data.push(val);
+        }
+        l_indptr[j + 1] = l_indices.len();
+        u_indptr[j + 1] = u_indices.len();
+    }
+
+    let l = CsMatOwned::new_csc((n, n), l_indptr, l_indices, l_data);
+    let u = CsMatOwned::new_csc((n, n), u_indptr, u_indices, u_data);
+    Ok(LuFactorization {
+        l: l,
+        u: u,
+        perm: perm,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use sparse::CsMatOwned;
+
+    #[test]
+    fn lu_identity() {
+        let eye = CsMatOwned::new_csc((3, 3),
+                                      vec![0, 1, 2, 3],
+                                      vec![0, 1, 2],
+                                      vec![1., 1., 1.]);
+        let lu = super::lu(eye.view()).unwrap();
+        let x = lu.solve(&[1., 2., 3.]).unwrap();
+        assert_eq!(x, vec![1., 2., 3.]);
+    }
+
+    #[test]
+    fn lu_with_pivoting() {
+        // |2 4 0|          the first column needs a pivot swap (|4| > |2|);
+        // |4 4 0|          all intermediate divisions (0.5, 4/4, ...) are
+        // |0 0 2|          exact in binary floating point
+        let a = CsMatOwned::new_csc((3, 3),
+                                    vec![0, 2, 4, 5],
+                                    vec![0, 1, 0, 1, 2],
+                                    vec![2., 4., 4., 4., 2.]);
+        let lu = super::lu(a.view()).unwrap();
+
+        // b = A * [1, 2, 3]
+        let b = vec![10., 12., 6.];
+        let x = lu.solve(&b).unwrap();
+        assert_eq!(x, vec![1., 2., 3.]);
+    }
+}
@@ -0,0 +1,11 @@
+/// Sparse linear algebra
+///
+/// This module gathers the sparse direct solvers built on top of the
+/// triangular solve kernels: the triangular solves themselves, and the
+/// factorizations (LU, Cholesky) that reduce a general system to a
+/// sequence of triangular solves.
+
+pub mod trisolve;
+pub mod lu;
+pub mod etree;
+pub mod cholesky;
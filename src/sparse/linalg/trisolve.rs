@@ -7,6 +7,67 @@ use sparse::vec::{self, VecDim};
 use errors::SprsError;
 use stack::{self, StackVal, DStack};
 
+/// How a matrix should be interpreted by a triangular solve: as itself,
+/// transposed, conjugated, or conjugate-transposed.
+///
+/// A transposed csc (resp. csr) lower triangular solve is implemented by
+/// reusing the traversal of a csr (resp. csc) upper triangular solve
+/// directly on the original storage, since csc-by-columns and
+/// csr-by-rows are structurally identical: no `.transpose_view()` call
+/// or reallocation is needed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    NoTranspose,
+    Transpose,
+    Conjugate,
+    ConjTranspose,
+}
+
+impl Op {
+    fn is_transpose(self) -> bool {
+        match self {
+            Op::Transpose | Op::ConjTranspose => true,
+            Op::NoTranspose | Op::Conjugate => false,
+        }
+    }
+
+    fn is_conjugate(self) -> bool {
+        match self {
+            Op::Conjugate | Op::ConjTranspose => true,
+            Op::NoTranspose | Op::Transpose => false,
+        }
+    }
+}
+
+/// Types whose values can be conjugated. Real scalar types are their own
+/// conjugate; complex types conjugate their imaginary part.
+pub trait Conjugate: Copy {
+    fn conj(self) -> Self;
+}
+
+macro_rules! impl_conjugate_as_self {
+    ($($t:ty),*) => {
+        $(impl Conjugate for $t {
+            fn conj(self) -> Self { self }
+        })*
+    }
+}
+
+impl_conjugate_as_self!(f32, f64, i8, i16, i32, i64, isize, u8, u16, u32,
+                         u64, usize);
+
+impl<N: Clone + Num + ::std::ops::Neg<Output = N>> Conjugate
+    for ::num::complex::Complex<N> {
+    fn conj(self) -> Self {
+        ::num::complex::Complex::conj(&self)
+    }
+}
+
+#[inline]
+fn maybe_conj<N: Conjugate>(val: N, op: Op) -> N {
+    if op.is_conjugate() { val.conj() } else { val }
+}
+
 fn check_solver_dimensions<N, V: ?Sized>(lower_tri_mat: &CsMatView<N>, rhs: &V)
 where N: Copy + Num,
       V: vec::VecDim<N>
@@ -20,6 +81,22 @@ where N: Copy + Num,
     }
 }
 
+/// Same checks as `check_solver_dimensions`, for a dense matrix rhs
+/// stored as a flat, column-major `&[N]` of `n_rhs` columns.
+fn check_solver_dimensions_mat<N>(lower_tri_mat: &CsMatView<N>,
+                                   rhs: &[N],
+                                   n_rhs: usize)
+where N: Copy + Num
+{
+    let (cols, rows) = (lower_tri_mat.cols(), lower_tri_mat.rows());
+    if cols != rows {
+        panic!("Non square matrix passed to solver");
+    }
+    if rhs.len() != cols * n_rhs {
+        panic!("Dimension mismatch");
+    }
+}
+
 /// Solve a sparse lower triangular matrix system, with a csr matrix
 /// and a dense vector as inputs
 ///
@@ -66,6 +143,158 @@ where N: Copy + Num,
     Ok(())
 }
 
+/// Solve a sparse lower triangular matrix system, with a csr matrix and
+/// a dense vector as inputs, assuming an implicit unit diagonal.
+///
+/// This is the form in which sparse LU and Cholesky factors store their
+/// `L` factor: the diagonal is known to be `1` and may not even be
+/// present in the matrix's structure. Skipping the diagonal lookup and
+/// the final division avoids a spurious `SingularMatrix` error in that
+/// case, and saves a division per row.
+pub fn lsolve_csr_unit_dense_rhs<N, V: ?Sized>(lower_tri_mat: CsMatView<N>,
+                                               rhs: &mut V)
+                                               -> Result<(), SprsError>
+where N: Copy + Num,
+      V: IndexMut<usize, Output = N> + vec::VecDim<N>
+{
+    check_solver_dimensions(&lower_tri_mat, rhs);
+    if !lower_tri_mat.is_csr() {
+        panic!("Storage mismatch");
+    }
+
+    for (row_ind, row) in lower_tri_mat.outer_iterator().enumerate() {
+        let mut x = rhs[row_ind];
+        for (col_ind, &val) in row.iter() {
+            if col_ind >= row_ind {
+                continue;
+            }
+            x = x - val * rhs[col_ind];
+        }
+        rhs[row_ind] = x;
+    }
+    Ok(())
+}
+
+/// Solve a sparse lower triangular matrix system against several
+/// right-hand sides at once, with a csr matrix and a dense, column-major
+/// matrix as inputs.
+///
+/// `rhs` holds `n_rhs` columns of length `lower_tri_mat.rows()` stored
+/// contiguously column-major (column `k`, row `i` is `rhs[i + k * n]`).
+/// The results are written back into `rhs`. Solving every right-hand
+/// side in one call lets the matrix traversal happen once per row
+/// instead of once per right-hand side, which is the common case when a
+/// factorization is reused against many right-hand sides.
+pub fn lsolve_csr_dense_rhs_mat<N>(lower_tri_mat: CsMatView<N>,
+                                   rhs: &mut [N],
+                                   n_rhs: usize)
+                                   -> Result<(), SprsError>
+where N: Copy + Num
+{
+    check_solver_dimensions_mat(&lower_tri_mat, rhs, n_rhs);
+    if !lower_tri_mat.is_csr() {
+        panic!("Storage mismatch");
+    }
+    let n = lower_tri_mat.rows();
+
+    for (row_ind, row) in lower_tri_mat.outer_iterator().enumerate() {
+        let mut diag_val = N::zero();
+        for (col_ind, &val) in row.iter() {
+            if col_ind == row_ind {
+                diag_val = val;
+            }
+        }
+        if diag_val == N::zero() {
+            return Err(SprsError::SingularMatrix);
+        }
+        for (col_ind, &val) in row.iter() {
+            if col_ind >= row_ind {
+                continue;
+            }
+            for k in 0..n_rhs {
+                let base = k * n;
+                let c = rhs[base + col_ind];
+                rhs[base + row_ind] = rhs[base + row_ind] - val * c;
+            }
+        }
+        for k in 0..n_rhs {
+            let base = k * n;
+            rhs[base + row_ind] = rhs[base + row_ind] / diag_val;
+        }
+    }
+    Ok(())
+}
+
+/// Solve a sparse lower (or, with `op`, upper) triangular matrix system,
+/// with a csr matrix and a dense vector as inputs.
+///
+/// With `op` set to `Op::Transpose` or `Op::ConjTranspose`, this solves
+/// `lower_tri_mat^T x = b` (resp. `lower_tri_mat^H x = b`) directly: since
+/// a csr matrix traversed by row is structurally a csc matrix traversed
+/// by column, the transposed solve reuses the traversal of
+/// `usolve_csc_dense_rhs` applied to this matrix's own storage, with no
+/// `.transpose_view()` call and no reallocation. This lets a single LU
+/// or Cholesky factor be reused for both `A x = b` and `A^T x = b`.
+pub fn lsolve_csr_dense_rhs_op<N, V: ?Sized>(lower_tri_mat: CsMatView<N>,
+                                            rhs: &mut V,
+                                            op: Op)
+                                            -> Result<(), SprsError>
+where N: Copy + Num + Conjugate,
+      V: IndexMut<usize, Output = N> + vec::VecDim<N>
+{
+    check_solver_dimensions(&lower_tri_mat, rhs);
+    if !lower_tri_mat.is_csr() {
+        panic!("Storage mismatch");
+    }
+
+    if !op.is_transpose() {
+        for (row_ind, row) in lower_tri_mat.outer_iterator().enumerate() {
+            let mut diag_val = N::zero();
+            let mut x = rhs[row_ind];
+            for (col_ind, &val) in row.iter() {
+                let val = maybe_conj(val, op);
+                if col_ind == row_ind {
+                    diag_val = val;
+                    continue;
+                }
+                if col_ind > row_ind {
+                    continue;
+                }
+                x = x - val * rhs[col_ind];
+            }
+            if diag_val == N::zero() {
+                return Err(SprsError::SingularMatrix);
+            }
+            rhs[row_ind] = x / diag_val;
+        }
+        return Ok(());
+    }
+
+    // lower_tri_mat^T is upper triangular; its rows are this matrix's
+    // columns, so we reuse usolve_csc_dense_rhs's traversal (reverse
+    // outer iteration, diagonal looked up with `get`).
+    for (col_ind, row) in lower_tri_mat.outer_iterator().enumerate().rev() {
+        let diag_val = match row.get(col_ind) {
+            Some(&d) => maybe_conj(d, op),
+            None => N::zero(),
+        };
+        if diag_val == N::zero() {
+            return Err(SprsError::SingularMatrix);
+        }
+        let b = rhs[col_ind];
+        let x = b / diag_val;
+        rhs[col_ind] = x;
+        for (row_ind, &val) in row.iter() {
+            if row_ind >= col_ind {
+                continue;
+            }
+            let val = maybe_conj(val, op);
+            let b = rhs[row_ind];
+            rhs[row_ind] = b - val * x;
+        }
+    }
+    Ok(())
+}
 
 /// Solve a sparse lower triangular matrix system, with a csc matrix
 /// and a dense vector as inputs
@@ -102,7 +331,152 @@ where N: Copy + Num,
     Ok(())
 }
 
-fn lspsolve_csc_process_col<N: Copy + Num, V: ?Sized>
+/// Solve a sparse lower triangular matrix system, with a csc matrix and
+/// a dense vector as inputs, assuming an implicit unit diagonal.
+///
+/// This is the form in which sparse LU and Cholesky factors store their
+/// `L` factor: the diagonal is known to be `1` and may not even be
+/// present in the matrix's structure. This avoids the logarithmic
+/// diagonal search of `lsolve_csc_dense_rhs`, and the spurious
+/// `SingularMatrix` error that an absent diagonal would otherwise cause.
+pub fn lsolve_csc_unit_dense_rhs<N, V: ?Sized>(lower_tri_mat: CsMatView<N>,
+                                               rhs: &mut V)
+                                               -> Result<(), SprsError>
+where N: Copy + Num,
+      V: IndexMut<usize, Output = N> + vec::VecDim<N>
+{
+    check_solver_dimensions(&lower_tri_mat, rhs);
+    if !lower_tri_mat.is_csc() {
+        panic!("Storage mismatch");
+    }
+
+    for (col_ind, col) in lower_tri_mat.outer_iterator().enumerate() {
+        try!(lspsolve_csc_process_col_unit(col, col_ind, rhs));
+    }
+    Ok(())
+}
+
+/// Solve a sparse lower triangular matrix system against several
+/// right-hand sides at once, with a csc matrix and a dense, column-major
+/// matrix as inputs.
+///
+/// `rhs` holds `n_rhs` columns of length `lower_tri_mat.rows()` stored
+/// contiguously column-major (column `k`, row `i` is `rhs[i + k * n]`).
+/// The results are written back into `rhs`. Solving every right-hand
+/// side in one call lets the matrix traversal happen once per column
+/// instead of once per right-hand side, which is the common case when a
+/// factorization is reused against many right-hand sides.
+pub fn lsolve_csc_dense_rhs_mat<N>(lower_tri_mat: CsMatView<N>,
+                                   rhs: &mut [N],
+                                   n_rhs: usize)
+                                   -> Result<(), SprsError>
+where N: Copy + Num
+{
+    check_solver_dimensions_mat(&lower_tri_mat, rhs, n_rhs);
+    if !lower_tri_mat.is_csc() {
+        panic!("Storage mismatch");
+    }
+    let n = lower_tri_mat.rows();
+
+    for (col_ind, col) in lower_tri_mat.outer_iterator().enumerate() {
+        let diag_val = match col.get(col_ind) {
+            Some(&d) => d,
+            None => N::zero(),
+        };
+        if diag_val == N::zero() {
+            return Err(SprsError::SingularMatrix);
+        }
+        for k in 0..n_rhs {
+            let base = k * n;
+            rhs[base + col_ind] = rhs[base + col_ind] / diag_val;
+        }
+        for (row_ind, &val) in col.iter() {
+            if row_ind <= col_ind {
+                continue;
+            }
+            for k in 0..n_rhs {
+                let base = k * n;
+                let x = rhs[base + col_ind];
+                rhs[base + row_ind] = rhs[base + row_ind] - val * x;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Solve a sparse lower (or, with `op`, upper) triangular matrix system,
+/// with a csc matrix and a dense vector as inputs.
+///
+/// With `op` set to `Op::Transpose` or `Op::ConjTranspose`, this solves
+/// `lower_tri_mat^T x = b` (resp. `lower_tri_mat^H x = b`) directly: since
+/// a csc matrix traversed by column is structurally a csr matrix
+/// traversed by row, the transposed solve reuses the traversal of
+/// `usolve_csr_dense_rhs` applied to this matrix's own storage, with no
+/// `.transpose_view()` call and no reallocation. This lets a single LU
+/// or Cholesky factor be reused for both `A x = b` and `A^T x = b`.
+pub fn lsolve_csc_dense_rhs_op<N, V: ?Sized>(lower_tri_mat: CsMatView<N>,
+                                            rhs: &mut V,
+                                            op: Op)
+                                            -> Result<(), SprsError>
+where N: Copy + Num + Conjugate,
+      V: IndexMut<usize, Output = N> + vec::VecDim<N>
+{
+    check_solver_dimensions(&lower_tri_mat, rhs);
+    if !lower_tri_mat.is_csc() {
+        panic!("Storage mismatch");
+    }
+
+    if !op.is_transpose() {
+        for (col_ind, col) in lower_tri_mat.outer_iterator().enumerate() {
+            if let Some(&diag_val) = col.get(col_ind) {
+                let diag_val = maybe_conj(diag_val, op);
+                if diag_val == N::zero() {
+                    return Err(SprsError::SingularMatrix);
+                }
+                let b = rhs[col_ind];
+                let x = b / diag_val;
+                rhs[col_ind] = x;
+                for (row_ind, &val) in col.iter() {
+                    if row_ind <= col_ind {
+                        continue;
+                    }
+                    let val = maybe_conj(val, op);
+                    let b = rhs[row_ind];
+                    rhs[row_ind] = b - val * x;
+                }
+            } else {
+                return Err(SprsError::SingularMatrix);
+            }
+        }
+        return Ok(());
+    }
+
+    // lower_tri_mat^T is upper triangular; its columns are this matrix's
+    // rows, so we reuse usolve_csr_dense_rhs's traversal (reverse outer
+    // iteration, diagonal found by scanning).
+    for (row_ind, col) in lower_tri_mat.outer_iterator().enumerate().rev() {
+        let mut diag_val = N::zero();
+        let mut x = rhs[row_ind];
+        for (col_ind, &val) in col.iter() {
+            let val = maybe_conj(val, op);
+            if col_ind == row_ind {
+                diag_val = val;
+                continue;
+            }
+            if col_ind < row_ind {
+                continue;
+            }
+            x = x - val * rhs[col_ind];
+        }
+        if diag_val == N::zero() {
+            return Err(SprsError::SingularMatrix);
+        }
+        rhs[row_ind] = x / diag_val;
+    }
+    Ok(())
+}
+
+pub(crate) fn lspsolve_csc_process_col<N: Copy + Num, V: ?Sized>
                                                       (col: vec::CsVecView<N>,
                                                        col_ind: usize,
                                                        rhs: &mut V)
@@ -129,6 +503,24 @@ where V: vec::VecDim<N> + IndexMut<usize, Output = N>
     Ok(())
 }
 
+pub(crate) fn lspsolve_csc_process_col_unit<N: Copy + Num, V: ?Sized>
+                                                      (col: vec::CsVecView<N>,
+                                                       col_ind: usize,
+                                                       rhs: &mut V)
+                                                       -> Result<(), SprsError>
+where V: vec::VecDim<N> + IndexMut<usize, Output = N>
+{
+    let x = rhs[col_ind];
+    for (row_ind, &val) in col.iter() {
+        if row_ind <= col_ind {
+            continue;
+        }
+        let b = rhs[row_ind];
+        rhs[row_ind] = b - val * x;
+    }
+    Ok(())
+}
+
 /// Solve a sparse upper triangular matrix system, with a csc matrix
 /// and a dense vector as inputs
 ///
@@ -181,89 +573,372 @@ where N: Copy + Num,
     Ok(())
 }
 
-/// Solve a sparse lower triangular matrix system, with a csr matrix
-/// and a dense vector as inputs
-///
-/// The solve results are written into the provided values.
+/// Solve a sparse upper triangular matrix system, with a csc matrix and
+/// a dense vector as inputs, assuming an implicit unit diagonal.
 ///
-/// This solve does not assume the input matrix to actually be
-/// triangular, instead it ignores the upper triangular part.
-pub fn usolve_csr_dense_rhs<N, V: ?Sized>(upper_tri_mat: CsMatView<N>,
-                                          rhs: &mut V)
-                                          -> Result<(), SprsError>
+/// See `lsolve_csc_unit_dense_rhs` for the rationale: this skips the
+/// diagonal lookup and division entirely, treating the diagonal as `1`.
+pub fn usolve_csc_unit_dense_rhs<N, V: ?Sized>(upper_tri_mat: CsMatView<N>,
+                                               rhs: &mut V)
+                                               -> Result<(), SprsError>
 where N: Copy + Num,
       V: IndexMut<usize, Output = N> + vec::VecDim<N>
 {
     check_solver_dimensions(&upper_tri_mat, rhs);
-    if !upper_tri_mat.is_csr() {
+    if !upper_tri_mat.is_csc() {
         panic!("Storage mismatch");
     }
-    // we base our algorithm on the following decomposition:
-    // | u_0_0    u_0_1^T | | x_0 |    | b_0 |
-    // |   0      U_1_1   | | x_1 |  = | b_1 |
-    //
-    // At each step of the algorithm, the x_1 part is known from previous
-    // iterations and x_0 can be computed as
-    // x0 = (b_0 - u_0_1^T.x_1) / u_0_0
-    for (row_ind, row) in upper_tri_mat.outer_iterator().enumerate().rev() {
-        let mut diag_val = N::zero();
-        let mut x = rhs[row_ind];
-        for (col_ind, &val) in row.iter() {
-            if col_ind == row_ind {
-                diag_val = val;
-                continue;
-            }
-            if col_ind < row_ind {
+
+    for (col_ind, col) in upper_tri_mat.outer_iterator().enumerate().rev() {
+        let x = rhs[col_ind];
+        for (row_ind, &val) in col.iter() {
+            if row_ind >= col_ind {
                 continue;
             }
-            x = x - val * rhs[col_ind];
+            let b = rhs[row_ind];
+            rhs[row_ind] = b - val * x;
         }
+    }
+
+    Ok(())
+}
+
+/// Solve a sparse upper triangular matrix system against several
+/// right-hand sides at once, with a csc matrix and a dense, column-major
+/// matrix as inputs.
+///
+/// `rhs` holds `n_rhs` columns of length `upper_tri_mat.rows()` stored
+/// contiguously column-major (column `k`, row `i` is `rhs[i + k * n]`).
+/// The results are written back into `rhs`. Solving every right-hand
+/// side in one call lets the matrix traversal happen once per column
+/// instead of once per right-hand side, which is the common case when a
+/// factorization is reused against many right-hand sides.
+pub fn usolve_csc_dense_rhs_mat<N>(upper_tri_mat: CsMatView<N>,
+                                   rhs: &mut [N],
+                                   n_rhs: usize)
+                                   -> Result<(), SprsError>
+where N: Copy + Num
+{
+    check_solver_dimensions_mat(&upper_tri_mat, rhs, n_rhs);
+    if !upper_tri_mat.is_csc() {
+        panic!("Storage mismatch");
+    }
+    let n = upper_tri_mat.rows();
+
+    for (col_ind, col) in upper_tri_mat.outer_iterator().enumerate().rev() {
+        let diag_val = match col.get(col_ind) {
+            Some(&d) => d,
+            None => N::zero(),
+        };
         if diag_val == N::zero() {
             return Err(SprsError::SingularMatrix);
         }
-        rhs[row_ind] = x / diag_val;
+        for k in 0..n_rhs {
+            let base = k * n;
+            rhs[base + col_ind] = rhs[base + col_ind] / diag_val;
+        }
+        for (row_ind, &val) in col.iter() {
+            if row_ind >= col_ind {
+                continue;
+            }
+            for k in 0..n_rhs {
+                let base = k * n;
+                let x = rhs[base + col_ind];
+                rhs[base + row_ind] = rhs[base + row_ind] - val * x;
+            }
+        }
     }
+
     Ok(())
 }
 
-/// Sparse triangular CSC / sparse vector solve
+/// Solve a sparse upper (or, with `op`, lower) triangular matrix system,
+/// with a csc matrix and a dense vector as inputs.
 ///
-/// lower_tri_mat is a sparse lower triangular matrix of shape (n, n)
-/// rhs is a sparse vector of size n
-/// dstack is a double stack with capacity 2*n
-/// x_workspace is a workspace vector with length equal to the number of
-/// rows of lower_tri_mat. Its input values can be anything.
-/// visited is a workspace vector of same size as upper_tri_mat.indptr(),
-/// and should be all false.
+/// With `op` set to `Op::Transpose` or `Op::ConjTranspose`, this solves
+/// `upper_tri_mat^T x = b` (resp. `upper_tri_mat^H x = b`) directly:
+/// since a csc matrix traversed by column is structurally a csr matrix
+/// traversed by row, the transposed solve reuses the traversal of
+/// `lsolve_csr_dense_rhs` applied to this matrix's own storage, with no
+/// `.transpose_view()` call and no reallocation. This lets a single LU
+/// or Cholesky factor be reused for both `A x = b` and `A^T x = b`.
+pub fn usolve_csc_dense_rhs_op<N, V: ?Sized>(upper_tri_mat: CsMatView<N>,
+                                             rhs: &mut V,
+                                             op: Op)
+                                             -> Result<(), SprsError>
+where N: Copy + Num + Conjugate,
+      V: IndexMut<usize, Output = N> + vec::VecDim<N>
+{
+    check_solver_dimensions(&upper_tri_mat, rhs);
+    if !upper_tri_mat.is_csc() {
+        panic!("Storage mismatch");
+    }
+
+    if !op.is_transpose() {
+        for (col_ind, col) in upper_tri_mat.outer_iterator().enumerate().rev() {
+            if let Some(&diag_val) = col.get(col_ind) {
+                let diag_val = maybe_conj(diag_val, op);
+                if diag_val == N::zero() {
+                    return Err(SprsError::SingularMatrix);
+                }
+                let b = rhs[col_ind];
+                let x = b / diag_val;
+                rhs[col_ind] = x;
+                for (row_ind, &val) in col.iter() {
+                    if row_ind >= col_ind {
+                        continue;
+                    }
+                    let val = maybe_conj(val, op);
+                    let b = rhs[row_ind];
+                    rhs[row_ind] = b - val * x;
+                }
+            } else {
+                return Err(SprsError::SingularMatrix);
+            }
+        }
+        return Ok(());
+    }
+
+    // upper_tri_mat^T is lower triangular; its columns are this matrix's
+    // rows, so we reuse lsolve_csr_dense_rhs's traversal (forward outer
+    // iteration, diagonal found by scanning).
+    for (row_ind, col) in upper_tri_mat.outer_iterator().enumerate() {
+        let mut diag_val = N::zero();
+        let mut x = rhs[row_ind];
+        for (col_ind, &val) in col.iter() {
+            let val = maybe_conj(val, op);
+            if col_ind == row_ind {
+                diag_val = val;
+                continue;
+            }
+            if col_ind > row_ind {
+                continue;
+            }
+            x = x - val * rhs[col_ind];
+        }
+        if diag_val == N::zero() {
+            return Err(SprsError::SingularMatrix);
+        }
+        rhs[row_ind] = x / diag_val;
+    }
+    Ok(())
+}
+
+/// Solve a sparse upper triangular matrix system, with a csr matrix
+/// and a dense vector as inputs
 ///
-/// On succesful execution, dstack will hold the non-zero pattern in its
-/// right stack, and x_workspace will contain the solve values at the indices
-/// contained in right stack. The non-zero pattern indices are not guaranteed
-/// to be sorted (they are sorted for each connected component of the matrix's
-/// graph).
+/// The solve results are written into the provided values.
 ///
-/// # Panics
+/// This solve does not assume the input matrix to actually be
+/// triangular, instead it ignores the lower triangular part.
+pub fn usolve_csr_dense_rhs<N, V: ?Sized>(upper_tri_mat: CsMatView<N>,
+                                          rhs: &mut V)
+                                          -> Result<(), SprsError>
+where N: Copy + Num,
+      V: IndexMut<usize, Output = N> + vec::VecDim<N>
+{
+    check_solver_dimensions(&upper_tri_mat, rhs);
+    if !upper_tri_mat.is_csr() {
+        panic!("Storage mismatch");
+    }
+    // we base our algorithm on the following decomposition:
+    // | u_0_0    u_0_1^T | | x_0 |    | b_0 |
+    // |   0      U_1_1   | | x_1 |  = | b_1 |
+    //
+    // At each step of the algorithm, the x_1 part is known from previous
+    // iterations and x_0 can be computed as
+    // x0 = (b_0 - u_0_1^T.x_1) / u_0_0
+    for (row_ind, row) in upper_tri_mat.outer_iterator().enumerate().rev() {
+        let mut diag_val = N::zero();
+        let mut x = rhs[row_ind];
+        for (col_ind, &val) in row.iter() {
+            if col_ind == row_ind {
+                diag_val = val;
+                continue;
+            }
+            if col_ind < row_ind {
+                continue;
+            }
+            x = x - val * rhs[col_ind];
+        }
+        if diag_val == N::zero() {
+            return Err(SprsError::SingularMatrix);
+        }
+        rhs[row_ind] = x / diag_val;
+    }
+    Ok(())
+}
+
+/// Solve a sparse upper triangular matrix system, with a csr matrix and
+/// a dense vector as inputs, assuming an implicit unit diagonal.
 ///
-/// * if dstack.capacity() is too small
-/// * if dstack is not empty
-/// * if w_workspace is not of length n
+/// See `lsolve_csc_unit_dense_rhs` for the rationale: this skips the
+/// diagonal lookup and division entirely, treating the diagonal as `1`.
+pub fn usolve_csr_unit_dense_rhs<N, V: ?Sized>(upper_tri_mat: CsMatView<N>,
+                                               rhs: &mut V)
+                                               -> Result<(), SprsError>
+where N: Copy + Num,
+      V: IndexMut<usize, Output = N> + vec::VecDim<N>
+{
+    check_solver_dimensions(&upper_tri_mat, rhs);
+    if !upper_tri_mat.is_csr() {
+        panic!("Storage mismatch");
+    }
+    for (row_ind, row) in upper_tri_mat.outer_iterator().enumerate().rev() {
+        let mut x = rhs[row_ind];
+        for (col_ind, &val) in row.iter() {
+            if col_ind <= row_ind {
+                continue;
+            }
+            x = x - val * rhs[col_ind];
+        }
+        rhs[row_ind] = x;
+    }
+    Ok(())
+}
+
+/// Solve a sparse upper triangular matrix system against several
+/// right-hand sides at once, with a csr matrix and a dense, column-major
+/// matrix as inputs.
 ///
-pub fn lsolve_csc_sparse_rhs<N>(lower_tri_mat: CsMatView<N>,
-                                rhs: vec::CsVecView<N>,
-                                dstack: &mut DStack<StackVal<usize>>,
-                                x_workspace: &mut [N],
-                                visited: &mut [bool])
-                                -> Result<(), SprsError>
+/// `rhs` holds `n_rhs` columns of length `upper_tri_mat.rows()` stored
+/// contiguously column-major (column `k`, row `i` is `rhs[i + k * n]`).
+/// The results are written back into `rhs`. Solving every right-hand
+/// side in one call lets the matrix traversal happen once per row
+/// instead of once per right-hand side, which is the common case when a
+/// factorization is reused against many right-hand sides.
+pub fn usolve_csr_dense_rhs_mat<N>(upper_tri_mat: CsMatView<N>,
+                                   rhs: &mut [N],
+                                   n_rhs: usize)
+                                   -> Result<(), SprsError>
 where N: Copy + Num
 {
-    if !lower_tri_mat.is_csc() {
+    check_solver_dimensions_mat(&upper_tri_mat, rhs, n_rhs);
+    if !upper_tri_mat.is_csr() {
+        panic!("Storage mismatch");
+    }
+    let n = upper_tri_mat.rows();
+
+    for (row_ind, row) in upper_tri_mat.outer_iterator().enumerate().rev() {
+        let mut diag_val = N::zero();
+        for (col_ind, &val) in row.iter() {
+            if col_ind == row_ind {
+                diag_val = val;
+            }
+        }
+        if diag_val == N::zero() {
+            return Err(SprsError::SingularMatrix);
+        }
+        for (col_ind, &val) in row.iter() {
+            if col_ind <= row_ind {
+                continue;
+            }
+            for k in 0..n_rhs {
+                let base = k * n;
+                let c = rhs[base + col_ind];
+                rhs[base + row_ind] = rhs[base + row_ind] - val * c;
+            }
+        }
+        for k in 0..n_rhs {
+            let base = k * n;
+            rhs[base + row_ind] = rhs[base + row_ind] / diag_val;
+        }
+    }
+    Ok(())
+}
+
+/// Solve a sparse upper (or, with `op`, lower) triangular matrix system,
+/// with a csr matrix and a dense vector as inputs.
+///
+/// With `op` set to `Op::Transpose` or `Op::ConjTranspose`, this solves
+/// `upper_tri_mat^T x = b` (resp. `upper_tri_mat^H x = b`) directly:
+/// since a csr matrix traversed by row is structurally a csc matrix
+/// traversed by column, the transposed solve reuses the traversal of
+/// `lsolve_csc_dense_rhs` applied to this matrix's own storage, with no
+/// `.transpose_view()` call and no reallocation. This lets a single LU
+/// or Cholesky factor be reused for both `A x = b` and `A^T x = b`.
+pub fn usolve_csr_dense_rhs_op<N, V: ?Sized>(upper_tri_mat: CsMatView<N>,
+                                             rhs: &mut V,
+                                             op: Op)
+                                             -> Result<(), SprsError>
+where N: Copy + Num + Conjugate,
+      V: IndexMut<usize, Output = N> + vec::VecDim<N>
+{
+    check_solver_dimensions(&upper_tri_mat, rhs);
+    if !upper_tri_mat.is_csr() {
+        panic!("Storage mismatch");
+    }
+
+    if !op.is_transpose() {
+        for (row_ind, row) in upper_tri_mat.outer_iterator().enumerate().rev() {
+            let mut diag_val = N::zero();
+            let mut x = rhs[row_ind];
+            for (col_ind, &val) in row.iter() {
+                let val = maybe_conj(val, op);
+                if col_ind == row_ind {
+                    diag_val = val;
+                    continue;
+                }
+                if col_ind < row_ind {
+                    continue;
+                }
+                x = x - val * rhs[col_ind];
+            }
+            if diag_val == N::zero() {
+                return Err(SprsError::SingularMatrix);
+            }
+            rhs[row_ind] = x / diag_val;
+        }
+        return Ok(());
+    }
+
+    // upper_tri_mat^T is lower triangular; its rows are this matrix's
+    // columns, so we reuse lsolve_csc_dense_rhs's traversal (forward
+    // outer iteration, diagonal looked up with `get`).
+    for (col_ind, row) in upper_tri_mat.outer_iterator().enumerate() {
+        if let Some(&diag_val) = row.get(col_ind) {
+            let diag_val = maybe_conj(diag_val, op);
+            if diag_val == N::zero() {
+                return Err(SprsError::SingularMatrix);
+            }
+            let b = rhs[col_ind];
+            let x = b / diag_val;
+            rhs[col_ind] = x;
+            for (row_ind, &val) in row.iter() {
+                if row_ind <= col_ind {
+                    continue;
+                }
+                let val = maybe_conj(val, op);
+                let b = rhs[row_ind];
+                rhs[row_ind] = b - val * x;
+            }
+        } else {
+            return Err(SprsError::SingularMatrix);
+        }
+    }
+    Ok(())
+}
+
+// The symbolic phase shared by `lsolve_csc_sparse_rhs` and
+// `usolve_csc_sparse_rhs`: a lower triangular matrix's columns only hold
+// entries at rows >= col_ind and an upper triangular matrix's columns
+// only hold entries at rows <= col_ind, but the dfs only cares about a
+// column's entries being its children in the elimination graph, so the
+// very same traversal discovers the reachable set for either case.
+fn sparse_reach<N>(mat: CsMatView<N>,
+                   rhs: vec::CsVecView<N>,
+                   dstack: &mut DStack<StackVal<usize>>,
+                   visited: &mut [bool])
+                   -> Result<(), SprsError>
+where N: Copy + Num
+{
+    if !mat.is_csc() {
         return Err(SprsError::BadStorageType);
     }
-    let n = lower_tri_mat.rows();
+    let n = mat.rows();
     assert!(dstack.capacity() >= 2 * n, "dstack cap should be 2*n");
     assert!(dstack.is_left_empty() && dstack.is_right_empty(),
             "dstack should be empty");
-    assert!(x_workspace.len() == n, "x should be of len n");
 
     // the solve works out the sparsity of the solution using depth first
     // search on the matrix's graph
@@ -290,7 +965,7 @@ where N: Copy + Num
                     }
                     visited[ind] = true;
                     dstack.push_left(StackVal::Exit(ind));
-                    if let Some(column) = lower_tri_mat.outer_view(ind) {
+                    if let Some(column) = mat.outer_view(ind) {
                         for (child_ind, _) in column.iter() {
                             dstack.push_left(StackVal::Enter(child_ind));
                         }
@@ -305,88 +980,637 @@ where N: Copy + Num
         }
     }
 
-    // solve for the non-zero values into dense workspace
-    rhs.scatter(x_workspace);
-    for &ind in dstack.iter_right().map(stack::extract_stack_val) {
-        println!("ind: {}", ind);
-        let col = lower_tri_mat.outer_view(ind).expect("ind not in bounds");
-        try!(lspsolve_csc_process_col(col, ind, x_workspace));
+    Ok(())
+}
+
+/// Symbolic phase of `lsolve_csc_sparse_rhs`: compute the non-zero
+/// pattern of the solve into `dstack`'s right stack, without touching
+/// `x_workspace`. Splitting this out lets the reachability computation be
+/// cached and reused by `lsolve_csc_solve_reached` across several solves
+/// that share the same factor and the same RHS sparsity pattern.
+///
+/// dstack is a double stack with capacity 2*n
+/// visited is a workspace vector of same size as lower_tri_mat.indptr(),
+/// and should be all false.
+///
+/// # Panics
+///
+/// * if dstack.capacity() is too small
+/// * if dstack is not empty
+///
+pub fn lsolve_csc_reach<N>(lower_tri_mat: CsMatView<N>,
+                          rhs: vec::CsVecView<N>,
+                          dstack: &mut DStack<StackVal<usize>>,
+                          visited: &mut [bool])
+                          -> Result<(), SprsError>
+where N: Copy + Num
+{
+    sparse_reach(lower_tri_mat, rhs, dstack, visited)
+}
+
+/// Numeric phase of `lsolve_csc_sparse_rhs`: given a non-zero pattern
+/// already computed by `lsolve_csc_reach` into `dstack`'s right stack,
+/// scatter `rhs` into `x_workspace` and eliminate each column of the
+/// pattern in order.
+///
+/// x_workspace is a workspace vector with length equal to the number of
+/// rows of lower_tri_mat. Its input values can be anything.
+pub fn lsolve_csc_solve_reached<N>(lower_tri_mat: CsMatView<N>,
+                                   rhs: vec::CsVecView<N>,
+                                   dstack: &DStack<StackVal<usize>>,
+                                   x_workspace: &mut [N])
+                                   -> Result<(), SprsError>
+where N: Copy + Num
+{
+    // indices reached only through the matrix's graph are not set by the
+    // scatter, and start from zero
+    for &ind in dstack.iter_right().map(stack::extract_stack_val) {
+        x_workspace[ind] = N::zero();
+    }
+    rhs.scatter(x_workspace);
+    for &ind in dstack.iter_right().map(stack::extract_stack_val) {
+        let col = lower_tri_mat.outer_view(ind).expect("ind not in bounds");
+        try!(lspsolve_csc_process_col(col, ind, x_workspace));
+    }
+
+    Ok(())
+}
+
+/// Sparse triangular CSC / sparse vector solve
+///
+/// lower_tri_mat is a sparse lower triangular matrix of shape (n, n)
+/// rhs is a sparse vector of size n
+/// dstack is a double stack with capacity 2*n
+/// x_workspace is a workspace vector with length equal to the number of
+/// rows of lower_tri_mat. Its input values can be anything.
+/// visited is a workspace vector of same size as lower_tri_mat.indptr(),
+/// and should be all false.
+///
+/// On succesful execution, dstack will hold the non-zero pattern in its
+/// right stack, and x_workspace will contain the solve values at the indices
+/// contained in right stack. The non-zero pattern indices are not guaranteed
+/// to be sorted (they are sorted for each connected component of the matrix's
+/// graph). Use `lsolve_csc_sparse_rhs_sorted` to get a globally sorted
+/// pattern assembled into a `CsVecOwned`.
+///
+/// This is `lsolve_csc_reach` followed by `lsolve_csc_solve_reached`; see
+/// these for a version split across a cacheable symbolic phase and a
+/// numeric phase.
+///
+/// # Panics
+///
+/// * if dstack.capacity() is too small
+/// * if dstack is not empty
+/// * if x_workspace is not of length n
+///
+pub fn lsolve_csc_sparse_rhs<N>(lower_tri_mat: CsMatView<N>,
+                                rhs: vec::CsVecView<N>,
+                                dstack: &mut DStack<StackVal<usize>>,
+                                x_workspace: &mut [N],
+                                visited: &mut [bool])
+                                -> Result<(), SprsError>
+where N: Copy + Num
+{
+    assert!(x_workspace.len() == lower_tri_mat.rows(), "x should be of len n");
+    try!(lsolve_csc_reach(lower_tri_mat, rhs, dstack, visited));
+    lsolve_csc_solve_reached(lower_tri_mat, rhs, dstack, x_workspace)
+}
+
+/// `lsolve_csc_sparse_rhs`, returning the result as a `CsVecOwned` whose
+/// non-zero pattern is globally sorted, ready to be used as-is instead of
+/// requiring a post-sort by the caller.
+pub fn lsolve_csc_sparse_rhs_sorted<N>(lower_tri_mat: CsMatView<N>,
+                                       rhs: vec::CsVecView<N>,
+                                       dstack: &mut DStack<StackVal<usize>>,
+                                       x_workspace: &mut [N],
+                                       visited: &mut [bool])
+                                       -> Result<vec::CsVecOwned<N>, SprsError>
+where N: Copy + Num
+{
+    try!(lsolve_csc_sparse_rhs(lower_tri_mat, rhs, dstack, x_workspace, visited));
+    let mut pattern: Vec<usize> = dstack.iter_right()
+                                        .map(stack::extract_stack_val)
+                                        .cloned()
+                                        .collect();
+    pattern.sort();
+    let data: Vec<N> = pattern.iter().map(|&ind| x_workspace[ind]).collect();
+    Ok(vec::CsVecOwned::new(lower_tri_mat.rows(), pattern, data))
+}
+
+fn uspsolve_csc_process_col<N: Copy + Num>(col: vec::CsVecView<N>,
+                                            col_ind: usize,
+                                            x_workspace: &mut [N])
+                                            -> Result<(), SprsError>
+{
+    if let Some(&diag_val) = col.get(col_ind) {
+        if diag_val == N::zero() {
+            return Err(SprsError::SingularMatrix);
+        }
+        let b = x_workspace[col_ind];
+        let x = b / diag_val;
+        x_workspace[col_ind] = x;
+        for (row_ind, &val) in col.iter() {
+            if row_ind >= col_ind {
+                continue;
+            }
+            let b = x_workspace[row_ind];
+            x_workspace[row_ind] = b - val * x;
+        }
+    } else {
+        return Err(SprsError::SingularMatrix);
+    }
+    Ok(())
+}
+
+/// Symbolic phase of `usolve_csc_sparse_rhs`, see `lsolve_csc_reach`. Since
+/// the dfs only cares about a column's entries being its children in the
+/// elimination graph, it is the very same traversal as `lsolve_csc_reach`,
+/// whether upper_tri_mat's columns hold entries at rows `<= col_ind` or
+/// not; only the numeric phase differs between the lower and upper
+/// triangular cases.
+///
+/// dstack is a double stack with capacity 2*n
+/// visited is a workspace vector of same size as upper_tri_mat.indptr(),
+/// and should be all false.
+///
+/// # Panics
+///
+/// * if dstack.capacity() is too small
+/// * if dstack is not empty
+///
+pub fn usolve_csc_reach<N>(upper_tri_mat: CsMatView<N>,
+                          rhs: vec::CsVecView<N>,
+                          dstack: &mut DStack<StackVal<usize>>,
+                          visited: &mut [bool])
+                          -> Result<(), SprsError>
+where N: Copy + Num
+{
+    sparse_reach(upper_tri_mat, rhs, dstack, visited)
+}
+
+/// Numeric phase of `usolve_csc_sparse_rhs`: given a non-zero pattern
+/// already computed by `usolve_csc_reach` into `dstack`'s right stack
+/// (each index ahead of the smaller indices its column updates), scatter
+/// `rhs` into `x_workspace` and eliminate each column of the pattern in
+/// back-substitution order.
+///
+/// x_workspace is a workspace vector with length equal to the number of
+/// rows of upper_tri_mat. Its input values can be anything.
+pub fn usolve_csc_solve_reached<N>(upper_tri_mat: CsMatView<N>,
+                                   rhs: vec::CsVecView<N>,
+                                   dstack: &DStack<StackVal<usize>>,
+                                   x_workspace: &mut [N])
+                                   -> Result<(), SprsError>
+where N: Copy + Num
+{
+    // indices reached only through the matrix's graph are not set by the
+    // scatter, and start from zero
+    for &ind in dstack.iter_right().map(stack::extract_stack_val) {
+        x_workspace[ind] = N::zero();
+    }
+    rhs.scatter(x_workspace);
+    for &ind in dstack.iter_right().map(stack::extract_stack_val) {
+        let col = upper_tri_mat.outer_view(ind).expect("ind not in bounds");
+        try!(uspsolve_csc_process_col(col, ind, x_workspace));
+    }
+
+    Ok(())
+}
+
+/// Sparse triangular CSC / sparse vector solve, upper triangular variant
+///
+/// upper_tri_mat is a sparse upper triangular matrix of shape (n, n)
+/// rhs is a sparse vector of size n
+/// dstack is a double stack with capacity 2*n
+/// x_workspace is a workspace vector with length equal to the number of
+/// rows of upper_tri_mat. Its input values can be anything.
+/// visited is a workspace vector of same size as upper_tri_mat.indptr(),
+/// and should be all false.
+///
+/// This mirrors `lsolve_csc_sparse_rhs`: the same DFS over the matrix's
+/// graph is used to discover the reachable nonzero set, but since
+/// upper_tri_mat's columns only ever hold entries at rows `<= col_ind`,
+/// the very same traversal naturally yields the reachable set in
+/// back-substitution order (each index ahead of the smaller indices its
+/// column updates, though not globally sorted) once numerically
+/// processed with `uspsolve_csc_process_col` rather than
+/// `lspsolve_csc_process_col`.
+///
+/// On succesful execution, dstack will hold the non-zero pattern in its
+/// right stack, and x_workspace will contain the solve values at the indices
+/// contained in right stack. The non-zero pattern indices are not guaranteed
+/// to be sorted (they are sorted for each connected component of the matrix's
+/// graph). Use `usolve_csc_sparse_rhs_sorted` to get a globally sorted
+/// pattern assembled into a `CsVecOwned`.
+///
+/// This is `usolve_csc_reach` followed by `usolve_csc_solve_reached`; see
+/// these for a version split across a cacheable symbolic phase and a
+/// numeric phase.
+///
+/// # Panics
+///
+/// * if dstack.capacity() is too small
+/// * if dstack is not empty
+/// * if x_workspace is not of length n
+///
+pub fn usolve_csc_sparse_rhs<N>(upper_tri_mat: CsMatView<N>,
+                                rhs: vec::CsVecView<N>,
+                                dstack: &mut DStack<StackVal<usize>>,
+                                x_workspace: &mut [N],
+                                visited: &mut [bool])
+                                -> Result<(), SprsError>
+where N: Copy + Num
+{
+    assert!(x_workspace.len() == upper_tri_mat.rows(), "x should be of len n");
+    try!(usolve_csc_reach(upper_tri_mat, rhs, dstack, visited));
+    usolve_csc_solve_reached(upper_tri_mat, rhs, dstack, x_workspace)
+}
+
+/// `usolve_csc_sparse_rhs`, returning the result as a `CsVecOwned` whose
+/// non-zero pattern is globally sorted, ready to be used as-is instead of
+/// requiring a post-sort by the caller.
+pub fn usolve_csc_sparse_rhs_sorted<N>(upper_tri_mat: CsMatView<N>,
+                                       rhs: vec::CsVecView<N>,
+                                       dstack: &mut DStack<StackVal<usize>>,
+                                       x_workspace: &mut [N],
+                                       visited: &mut [bool])
+                                       -> Result<vec::CsVecOwned<N>, SprsError>
+where N: Copy + Num
+{
+    try!(usolve_csc_sparse_rhs(upper_tri_mat, rhs, dstack, x_workspace, visited));
+    let mut pattern: Vec<usize> = dstack.iter_right()
+                                        .map(stack::extract_stack_val)
+                                        .cloned()
+                                        .collect();
+    pattern.sort();
+    let data: Vec<N> = pattern.iter().map(|&ind| x_workspace[ind]).collect();
+    Ok(vec::CsVecOwned::new(upper_tri_mat.rows(), pattern, data))
+}
+
+#[cfg(test)]
+mod test {
+
+    use sparse::{CsMatOwned, vec};
+    use stack::{self, DStack};
+    use std::collections::HashSet;
+    use num::complex::Complex;
+
+    #[test]
+    fn lsolve_csr_dense_rhs() {
+        // |1    | |3|   |3|
+        // |0 2  | |1| = |2|
+        // |1 0 1| |1|   |4|
+        let l = CsMatOwned::new((3, 3),
+                                vec![0, 1, 2, 4],
+                                vec![0, 1, 0, 2],
+                                vec![1, 2, 1, 1]);
+        let b = vec![3, 2, 4];
+        let mut x = b.clone();
+
+        super::lsolve_csr_dense_rhs(l.view(), &mut x).unwrap();
+        assert_eq!(x, vec![3, 1, 1]);
+    }
+
+    #[test]
+    fn lsolve_csc_dense_rhs() {
+        // |1    | |3|   |3|
+        // |1 2  | |1| = |5|
+        // |0 0 3| |1|   |3|
+        let l = CsMatOwned::new_csc((3, 3),
+                                    vec![0, 2, 3, 4],
+                                    vec![0, 1, 1, 2],
+                                    vec![1, 1, 2, 3]);
+        let b = vec![3, 5, 3];
+        let mut x = b.clone();
+
+        super::lsolve_csc_dense_rhs(l.view(), &mut x).unwrap();
+        assert_eq!(x, vec![3, 1, 1]);
+    }
+
+    #[test]
+    fn usolve_csc_dense_rhs() {
+        // |1 0 1| |3|   |4|
+        // |  2 0| |1| = |2|
+        // |    3| |1|   |3|
+        let u = CsMatOwned::new_csc((3, 3),
+                                    vec![0, 1, 2, 4],
+                                    vec![0, 1, 0, 2],
+                                    vec![1, 2, 1, 3]);
+        let b = vec![4, 2, 3];
+        let mut x = b.clone();
+
+        super::usolve_csc_dense_rhs(u.view(), &mut x).unwrap();
+        assert_eq!(x, vec![3, 1, 1]);
+    }
+
+    #[test]
+    fn usolve_csr_dense_rhs() {
+        // |1 1 0| |3|   |4|
+        // |  5 3| |1| = |8|
+        // |    1| |1|   |1|
+        let u = CsMatOwned::new((3, 3),
+                                vec![0, 2, 4, 5],
+                                vec![0, 1, 1, 2, 2],
+                                vec![1, 1, 5, 3, 1]);
+        let b = vec![4, 8, 1];
+        let mut x = b.clone();
+
+        super::usolve_csr_dense_rhs(u.view(), &mut x).unwrap();
+        assert_eq!(x, vec![3, 1, 1]);
+    }
+
+    #[test]
+    fn lsolve_csr_dense_rhs_mat() {
+        // same system as lsolve_csr_dense_rhs, solved for two distinct
+        // right-hand sides at once, so a bug in the per-column `k * n`
+        // offset (eg one column's result leaking into the other) would
+        // show up in only one of the two columns
+        let l = CsMatOwned::new((3, 3),
+                                vec![0, 1, 2, 4],
+                                vec![0, 1, 0, 2],
+                                vec![1, 2, 1, 1]);
+        let mut x = vec![3, 2, 4, 2, 6, 7];
+
+        super::lsolve_csr_dense_rhs_mat(l.view(), &mut x, 2).unwrap();
+        assert_eq!(x, vec![3, 1, 1, 2, 3, 5]);
     }
 
-    Ok(())
-}
+    #[test]
+    fn lsolve_csc_dense_rhs_mat() {
+        // same system as lsolve_csc_dense_rhs, solved for two distinct
+        // right-hand sides at once
+        let l = CsMatOwned::new_csc((3, 3),
+                                    vec![0, 2, 3, 4],
+                                    vec![0, 1, 1, 2],
+                                    vec![1, 1, 2, 3]);
+        let mut x = vec![3, 5, 3, 2, 10, 18];
 
-#[cfg(test)]
-mod test {
+        super::lsolve_csc_dense_rhs_mat(l.view(), &mut x, 2).unwrap();
+        assert_eq!(x, vec![3, 1, 1, 2, 4, 6]);
+    }
 
-    use sparse::{CsMatOwned, vec};
-    use stack::{self, DStack};
-    use std::collections::HashSet;
+    #[test]
+    fn usolve_csc_dense_rhs_mat() {
+        // same system as usolve_csc_dense_rhs, solved for two distinct
+        // right-hand sides at once
+        let u = CsMatOwned::new_csc((3, 3),
+                                    vec![0, 1, 2, 4],
+                                    vec![0, 1, 0, 2],
+                                    vec![1, 2, 1, 3]);
+        let mut x = vec![4, 2, 3, 7, 8, 6];
+
+        super::usolve_csc_dense_rhs_mat(u.view(), &mut x, 2).unwrap();
+        assert_eq!(x, vec![3, 1, 1, 5, 4, 2]);
+    }
 
     #[test]
-    fn lsolve_csr_dense_rhs() {
+    fn usolve_csr_dense_rhs_mat() {
+        // same system as usolve_csr_dense_rhs, solved for two distinct
+        // right-hand sides at once
+        let u = CsMatOwned::new((3, 3),
+                                vec![0, 2, 4, 5],
+                                vec![0, 1, 1, 2, 2],
+                                vec![1, 1, 5, 3, 1]);
+        let mut x = vec![4, 8, 1, 8, 22, 4];
+
+        super::usolve_csr_dense_rhs_mat(u.view(), &mut x, 2).unwrap();
+        assert_eq!(x, vec![3, 1, 1, 6, 2, 4]);
+    }
+
+    #[test]
+    fn lsolve_csr_unit_dense_rhs() {
+        // same system as lsolve_csr_dense_rhs, but with an implicit
+        // unit diagonal (the stored diagonal values are ignored)
         // |1    | |3|   |3|
-        // |0 2  | |1| = |2|
+        // |0 1  | |4| = |4|
         // |1 0 1| |1|   |4|
         let l = CsMatOwned::new((3, 3),
                                 vec![0, 1, 2, 4],
                                 vec![0, 1, 0, 2],
-                                vec![1, 2, 1, 1]);
-        let b = vec![3, 2, 4];
+                                vec![42, 2, 1, 42]);
+        let b = vec![3, 4, 4];
         let mut x = b.clone();
 
-        super::lsolve_csr_dense_rhs(l.view(), &mut x).unwrap();
-        assert_eq!(x, vec![3, 1, 1]);
+        super::lsolve_csr_unit_dense_rhs(l.view(), &mut x).unwrap();
+        assert_eq!(x, vec![3, 4, 1]);
     }
 
     #[test]
-    fn lsolve_csc_dense_rhs() {
+    fn lsolve_csc_unit_dense_rhs() {
+        // same system as lsolve_csc_dense_rhs, but with an implicit
+        // unit diagonal (the stored diagonal values are ignored)
         // |1    | |3|   |3|
-        // |1 2  | |1| = |5|
-        // |0 0 3| |1|   |3|
+        // |1 1  | |1| = |4|
+        // |0 0 1| |1|   |1|
         let l = CsMatOwned::new_csc((3, 3),
                                     vec![0, 2, 3, 4],
                                     vec![0, 1, 1, 2],
-                                    vec![1, 1, 2, 3]);
-        let b = vec![3, 5, 3];
+                                    vec![42, 1, 2, 42]);
+        let b = vec![3, 4, 1];
         let mut x = b.clone();
 
-        super::lsolve_csc_dense_rhs(l.view(), &mut x).unwrap();
+        super::lsolve_csc_unit_dense_rhs(l.view(), &mut x).unwrap();
         assert_eq!(x, vec![3, 1, 1]);
     }
 
     #[test]
-    fn usolve_csc_dense_rhs() {
+    fn usolve_csc_unit_dense_rhs() {
+        // same system as usolve_csc_dense_rhs, but with an implicit
+        // unit diagonal (the stored diagonal values are ignored)
         // |1 0 1| |3|   |4|
-        // |  2 0| |1| = |2|
-        // |    3| |1|   |3|
+        // |  1 0| |1| = |1|
+        // |    1| |1|   |1|
         let u = CsMatOwned::new_csc((3, 3),
                                     vec![0, 1, 2, 4],
                                     vec![0, 1, 0, 2],
-                                    vec![1, 2, 1, 3]);
-        let b = vec![4, 2, 3];
+                                    vec![42, 42, 1, 42]);
+        let b = vec![4, 1, 1];
         let mut x = b.clone();
 
-        super::usolve_csc_dense_rhs(u.view(), &mut x).unwrap();
+        super::usolve_csc_unit_dense_rhs(u.view(), &mut x).unwrap();
         assert_eq!(x, vec![3, 1, 1]);
     }
 
     #[test]
-    fn usolve_csr_dense_rhs() {
+    fn usolve_csr_unit_dense_rhs() {
+        // same system as usolve_csr_dense_rhs, but with an implicit
+        // unit diagonal (the stored diagonal values are ignored)
         // |1 1 0| |3|   |4|
-        // |  5 3| |1| = |8|
+        // |  1 3| |1| = |4|
         // |    1| |1|   |1|
         let u = CsMatOwned::new((3, 3),
                                 vec![0, 2, 4, 5],
                                 vec![0, 1, 1, 2, 2],
-                                vec![1, 1, 5, 3, 1]);
-        let b = vec![4, 8, 1];
+                                vec![42, 1, 42, 3, 42]);
+        let b = vec![4, 4, 1];
         let mut x = b.clone();
 
-        super::usolve_csr_dense_rhs(u.view(), &mut x).unwrap();
+        super::usolve_csr_unit_dense_rhs(u.view(), &mut x).unwrap();
+        assert_eq!(x, vec![3, 1, 1]);
+    }
+
+    #[test]
+    fn lsolve_csc_dense_rhs_op_no_transpose() {
+        // same system as lsolve_csc_dense_rhs, exercised through the
+        // NoTranspose branch of the _op variant rather than the plain
+        // solve
+        let l = CsMatOwned::new_csc((3, 3),
+                                    vec![0, 2, 3, 4],
+                                    vec![0, 1, 1, 2],
+                                    vec![1, 1, 2, 3]);
+        let mut x = vec![3, 5, 3];
+
+        super::lsolve_csc_dense_rhs_op(l.view(), &mut x, super::Op::NoTranspose)
+            .unwrap();
+        assert_eq!(x, vec![3, 1, 1]);
+    }
+
+    #[test]
+    fn lsolve_csr_dense_rhs_op_no_transpose() {
+        // same system as lsolve_csr_dense_rhs, exercised through the
+        // NoTranspose branch of the _op variant
+        let l = CsMatOwned::new((3, 3),
+                                vec![0, 1, 2, 4],
+                                vec![0, 1, 0, 2],
+                                vec![1, 2, 1, 1]);
+        let mut x = vec![3, 2, 4];
+
+        super::lsolve_csr_dense_rhs_op(l.view(), &mut x, super::Op::NoTranspose)
+            .unwrap();
+        assert_eq!(x, vec![3, 1, 1]);
+    }
+
+    #[test]
+    fn usolve_csc_dense_rhs_op_no_transpose() {
+        // same system as usolve_csc_dense_rhs, exercised through the
+        // NoTranspose branch of the _op variant
+        let u = CsMatOwned::new_csc((3, 3),
+                                    vec![0, 1, 2, 4],
+                                    vec![0, 1, 0, 2],
+                                    vec![1, 2, 1, 3]);
+        let mut x = vec![4, 2, 3];
+
+        super::usolve_csc_dense_rhs_op(u.view(), &mut x, super::Op::NoTranspose)
+            .unwrap();
+        assert_eq!(x, vec![3, 1, 1]);
+    }
+
+    #[test]
+    fn usolve_csr_dense_rhs_op_no_transpose() {
+        // same system as usolve_csr_dense_rhs, exercised through the
+        // NoTranspose branch of the _op variant
+        let u = CsMatOwned::new((3, 3),
+                                vec![0, 2, 4, 5],
+                                vec![0, 1, 1, 2, 2],
+                                vec![1, 1, 5, 3, 1]);
+        let mut x = vec![4, 8, 1];
+
+        super::usolve_csr_dense_rhs_op(u.view(), &mut x, super::Op::NoTranspose)
+            .unwrap();
         assert_eq!(x, vec![3, 1, 1]);
     }
 
+    #[test]
+    fn lsolve_csc_dense_rhs_op_conjugate() {
+        // l = |1+0i     0  ; 0+1i  2+0i|, solved through the NoTranspose
+        // branch with Op::Conjugate, so every value read off the matrix
+        // is conjugated before use: row 1's off-diagonal entry 0+1i is
+        // used as 0-1i
+        let l = CsMatOwned::new_csc((2, 2),
+                                    vec![0, 2, 3],
+                                    vec![0, 1, 1],
+                                    vec![Complex::new(1., 0.),
+                                         Complex::new(0., 1.),
+                                         Complex::new(2., 0.)]);
+        let mut x = vec![Complex::new(2., 0.), Complex::new(2., 0.)];
+
+        super::lsolve_csc_dense_rhs_op(l.view(), &mut x, super::Op::Conjugate)
+            .unwrap();
+        assert_eq!(x, vec![Complex::new(2., 0.), Complex::new(1., 1.)]);
+    }
+
+    #[test]
+    fn lsolve_csc_dense_rhs_op_conj_transpose() {
+        // l = |1+0i  0     0   ; 0+1i  2+0i  0  ; 0     0     3+0i|
+        // l^H = |1+0i  0-1i  0  ; 0  2+0i  0  ; 0  0  3+0i|
+        // l^H * [1+1i, 2+0i, 3+0i] = [1-1i, 4+0i, 9+0i]
+        let l = CsMatOwned::new_csc((3, 3),
+                                    vec![0, 2, 3, 4],
+                                    vec![0, 1, 1, 2],
+                                    vec![Complex::new(1., 0.),
+                                         Complex::new(0., 1.),
+                                         Complex::new(2., 0.),
+                                         Complex::new(3., 0.)]);
+        let mut x = vec![Complex::new(1., -1.),
+                         Complex::new(4., 0.),
+                         Complex::new(9., 0.)];
+
+        super::lsolve_csc_dense_rhs_op(l.view(), &mut x, super::Op::ConjTranspose)
+            .unwrap();
+        assert_eq!(x,
+                   vec![Complex::new(1., 1.), Complex::new(2., 0.), Complex::new(3., 0.)]);
+    }
+
+    #[test]
+    fn lsolve_csc_dense_rhs_op_transpose() {
+        // l = |1 0 0; 1 2 0; 0 0 3|, l^T = |1 1 0; 0 2 0; 0 0 3|
+        // l^T * [1, 2, 3] = [3, 4, 9]
+        let l = CsMatOwned::new_csc((3, 3),
+                                    vec![0, 2, 3, 4],
+                                    vec![0, 1, 1, 2],
+                                    vec![1, 1, 2, 3]);
+        let mut x = vec![3, 4, 9];
+
+        super::lsolve_csc_dense_rhs_op(l.view(), &mut x, super::Op::Transpose)
+            .unwrap();
+        assert_eq!(x, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn lsolve_csr_dense_rhs_op_transpose() {
+        // l = |1 0 0; 0 2 0; 1 0 1|, l^T = |1 0 1; 0 2 0; 0 0 1|
+        // l^T * [1, 2, 3] = [4, 4, 3]
+        let l = CsMatOwned::new((3, 3),
+                                vec![0, 1, 2, 4],
+                                vec![0, 1, 0, 2],
+                                vec![1, 2, 1, 1]);
+        let mut x = vec![4, 4, 3];
+
+        super::lsolve_csr_dense_rhs_op(l.view(), &mut x, super::Op::Transpose)
+            .unwrap();
+        assert_eq!(x, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn usolve_csc_dense_rhs_op_transpose() {
+        // u = |1 0 1; 0 2 0; 0 0 3|, u^T = |1 0 0; 0 2 0; 1 0 3|
+        // u^T * [1, 2, 3] = [1, 4, 10]
+        let u = CsMatOwned::new_csc((3, 3),
+                                    vec![0, 1, 2, 4],
+                                    vec![0, 1, 0, 2],
+                                    vec![1, 2, 1, 3]);
+        let mut x = vec![1, 4, 10];
+
+        super::usolve_csc_dense_rhs_op(u.view(), &mut x, super::Op::Transpose)
+            .unwrap();
+        assert_eq!(x, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn usolve_csr_dense_rhs_op_transpose() {
+        // u = |1 1 0; 0 5 3; 0 0 1|, u^T = |1 0 0; 1 5 0; 0 3 1|
+        // u^T * [1, 2, 3] = [1, 11, 9]
+        let u = CsMatOwned::new((3, 3),
+                                vec![0, 2, 4, 5],
+                                vec![0, 1, 1, 2, 2],
+                                vec![1, 1, 5, 3, 1]);
+        let mut x = vec![1, 11, 9];
+
+        super::usolve_csr_dense_rhs_op(u.view(), &mut x, super::Op::Transpose)
+            .unwrap();
+        assert_eq!(x, vec![1, 2, 3]);
+    }
+
     #[test]
     fn lspsolve_csc() {
         // |1        | | |   | |
@@ -459,4 +1683,153 @@ mod test {
 
         assert_eq!(x, expected_output);
     }
+
+    #[test]
+    fn uspsolve_csc() {
+        // |1 1      |
+        // |  2 3   2|
+        // |    3    |     solved first against b = 6 at row 2, then
+        // |      7 3|     against b = 70 at row 4
+        // |        5|
+        let u = CsMatOwned::new_csc((5, 5),
+                                    vec![0, 1, 3, 5, 6, 9],
+                                    vec![0, 0, 1, 1, 2, 3, 1, 3, 4],
+                                    vec![1, 1, 2, 3, 3, 7, 2, 3, 5]);
+
+        let b = vec::CsVecOwned::new(5, vec![2], vec![6]);
+        let mut xw = vec![1; 5]; // inital values should not matter
+        let mut visited = vec![false; 5]; // inital values matter here
+        let mut dstack = DStack::with_capacity(2 * 5);
+        super::usolve_csc_sparse_rhs(u.view(),
+                                     b.view(),
+                                     &mut dstack,
+                                     &mut xw,
+                                     &mut visited)
+            .unwrap();
+
+        let x: HashSet<_> = dstack.iter_right()
+                                  .map(stack::extract_stack_val)
+                                  .map(|&i| (i, xw[i]))
+                                  .collect();
+
+        let expected_output = vec::CsVecOwned::new(5,
+                                                   vec![0, 1, 2],
+                                                   vec![3, -3, 2]);
+        let expected_output = expected_output.to_set();
+
+        assert_eq!(x, expected_output);
+
+        let b = vec::CsVecOwned::new(5, vec![4], vec![70]);
+        let mut xw = vec![1; 5]; // inital values should not matter
+        let mut visited = vec![false; 5]; // inital values matter here
+        let mut dstack = DStack::with_capacity(2 * 5);
+        super::usolve_csc_sparse_rhs(u.view(),
+                                     b.view(),
+                                     &mut dstack,
+                                     &mut xw,
+                                     &mut visited)
+            .unwrap();
+
+        let x: HashSet<_> = dstack.iter_right()
+                                  .map(stack::extract_stack_val)
+                                  .map(|&i| (i, xw[i]))
+                                  .collect();
+
+        let expected_output = vec::CsVecOwned::new(5,
+                                                   vec![0, 1, 3, 4],
+                                                   vec![14, -14, -6, 14]);
+        let expected_output = expected_output.to_set();
+
+        assert_eq!(x, expected_output);
+    }
+
+    #[test]
+    fn lsolve_csc_sparse_rhs_sorted() {
+        let l = CsMatOwned::new_csc((5, 5),
+                                    vec![0, 2, 5, 6, 8, 9],
+                                    vec![0, 1, 1, 2, 4, 2, 3, 4, 4],
+                                    vec![1, 1, 2, 3, 2, 3, 7, 3, 5]);
+        let b = vec::CsVecOwned::new(5, vec![1, 2, 4], vec![4, 9, 9]);
+        let mut xw = vec![1; 5];
+        let mut visited = vec![false; 5];
+        let mut dstack = DStack::with_capacity(2 * 5);
+        let x = super::lsolve_csc_sparse_rhs_sorted(l.view(),
+                                                     b.view(),
+                                                     &mut dstack,
+                                                     &mut xw,
+                                                     &mut visited)
+            .unwrap();
+
+        let expected_output = vec::CsVecOwned::new(5, vec![1, 2, 4], vec![2, 1, 1]);
+        assert_eq!(x.to_set(), expected_output.to_set());
+    }
+
+    #[test]
+    fn usolve_csc_sparse_rhs_sorted() {
+        let u = CsMatOwned::new_csc((5, 5),
+                                    vec![0, 1, 3, 5, 6, 9],
+                                    vec![0, 0, 1, 1, 2, 3, 1, 3, 4],
+                                    vec![1, 1, 2, 3, 3, 7, 2, 3, 5]);
+        let b = vec::CsVecOwned::new(5, vec![4], vec![70]);
+        let mut xw = vec![1; 5];
+        let mut visited = vec![false; 5];
+        let mut dstack = DStack::with_capacity(2 * 5);
+        let x = super::usolve_csc_sparse_rhs_sorted(u.view(),
+                                                     b.view(),
+                                                     &mut dstack,
+                                                     &mut xw,
+                                                     &mut visited)
+            .unwrap();
+
+        let expected_output = vec::CsVecOwned::new(5,
+                                                    vec![0, 1, 3, 4],
+                                                    vec![14, -14, -6, 14]);
+        assert_eq!(x.to_set(), expected_output.to_set());
+    }
+
+    #[test]
+    fn lsolve_csc_reach_cached() {
+        // the symbolic phase can be computed once and the numeric phase
+        // rerun against a different rhs sharing the same sparsity pattern
+        let l = CsMatOwned::new_csc((5, 5),
+                                    vec![0, 2, 5, 6, 8, 9],
+                                    vec![0, 1, 1, 2, 4, 2, 3, 4, 4],
+                                    vec![1, 1, 2, 3, 2, 3, 7, 3, 5]);
+        let b = vec::CsVecOwned::new(5, vec![1, 2, 4], vec![4, 9, 9]);
+        let mut xw = vec![1; 5];
+        let mut visited = vec![false; 5];
+        let mut dstack = DStack::with_capacity(2 * 5);
+        super::lsolve_csc_reach(l.view(), b.view(), &mut dstack, &mut visited)
+            .unwrap();
+        super::lsolve_csc_solve_reached(l.view(), b.view(), &dstack, &mut xw)
+            .unwrap();
+
+        let x: HashSet<_> = dstack.iter_right()
+                                  .map(stack::extract_stack_val)
+                                  .map(|&i| (i, xw[i]))
+                                  .collect();
+
+        let expected_output = vec::CsVecOwned::new(5,
+                                                   vec![1, 2, 4],
+                                                   vec![2, 1, 1]);
+        let expected_output = expected_output.to_set();
+
+        assert_eq!(x, expected_output);
+
+        let b = vec::CsVecOwned::new(5, vec![1, 2, 4], vec![8, 18, 18]);
+        super::lsolve_csc_solve_reached(l.view(), b.view(), &dstack, &mut xw)
+            .unwrap();
+
+        let x: HashSet<_> = dstack.iter_right()
+                                  .map(stack::extract_stack_val)
+                                  .map(|&i| (i, xw[i]))
+                                  .collect();
+
+        let expected_output = vec::CsVecOwned::new(5,
+                                                   vec![1, 2, 4],
+                                                   vec![4, 2, 2]);
+        let expected_output = expected_output.to_set();
+
+        assert_eq!(x, expected_output);
+    }
 }